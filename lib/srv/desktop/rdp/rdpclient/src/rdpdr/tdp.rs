@@ -3,8 +3,11 @@ use rdp::model::error::RdpResult;
 use crate::{
     errors::try_error,
     util::{self, from_c_string, from_go_array},
-    CGOSharedDirectoryAnnounce, CGOSharedDirectoryCreateResponse, CGOSharedDirectoryInfoResponse,
-    CGOSharedDirectoryListResponse, CGOSharedDirectoryReadResponse,
+    CGOBatchResult, CGOChunkEntry, CGOSharedDirectoryAnnounce, CGOSharedDirectoryBatchResponse,
+    CGOSharedDirectoryChangeNotification, CGOSharedDirectoryChunkFetchResponse,
+    CGOSharedDirectoryChunkManifestResponse, CGOSharedDirectoryCreateResponse,
+    CGOSharedDirectoryInfoResponse, CGOSharedDirectoryListResponse, CGOSharedDirectoryReadResponse,
+    CGOSharedDirectoryRecursiveProgress, CGOSharedDirectoryRecursiveResponse,
 };
 
 use super::{path::UnixPath, ServerCreateDriveRequest};
@@ -93,6 +96,13 @@ pub struct FileSystemObject {
     pub file_type: FileType,
     pub is_empty: u8,
     pub path: UnixPath,
+    /// content_digest is the BLAKE3 digest of the file's entire contents,
+    /// used to drive content-addressed chunk transfer. None for
+    /// directories and for clients that don't compute it.
+    pub content_digest: Option<[u8; 32]>,
+    /// symlink_target is the path the entry points to when file_type is
+    /// FileType::Symlink, and None otherwise.
+    pub symlink_target: Option<UnixPath>,
 }
 
 impl FileSystemObject {
@@ -117,6 +127,10 @@ pub struct SharedDirectoryWriteRequest {
     pub offset: u64,
     pub path: UnixPath,
     pub write_data: Vec<u8>,
+    /// write_mode distinguishes a normal positional write from an
+    /// append, in which case offset is ignored and the data is written
+    /// at the file's current end-of-file.
+    pub write_mode: WriteMode,
 }
 
 impl std::fmt::Debug for SharedDirectoryWriteRequest {
@@ -127,6 +141,7 @@ impl std::fmt::Debug for SharedDirectoryWriteRequest {
             .field("offset", &self.offset)
             .field("path", &self.path)
             .field("write_data", &util::vec_u8_debug(&self.write_data))
+            .field("write_mode", &self.write_mode)
             .finish()
     }
 }
@@ -295,6 +310,419 @@ pub struct SharedDirectoryListRequest {
     pub path: UnixPath,
 }
 
+/// SharedDirectoryWatchRequest is sent by the TDP server to the client
+/// to request that a watch be established on path, so that the server
+/// is notified of changes instead of having to poll for them.
+#[derive(Debug)]
+pub struct SharedDirectoryWatchRequest {
+    pub completion_id: u32,
+    pub directory_id: u32,
+    pub path: UnixPath,
+    /// recursive indicates whether the watch should extend to
+    /// everything under path, not just path itself.
+    pub recursive: u8,
+    /// event_mask is a bitmask of ChangeEventType discriminants, letting
+    /// the server subscribe to only the event kinds it cares about.
+    pub event_mask: u32,
+}
+
+/// SharedDirectoryWatchResponse is sent by the TDP client to the server
+/// to acknowledge a SharedDirectoryWatchRequest was received and the
+/// watch was established.
+#[derive(Debug)]
+#[repr(C)]
+pub struct SharedDirectoryWatchResponse {
+    pub completion_id: u32,
+    pub err_code: TdpErrCode,
+}
+
+/// SharedDirectoryUnwatchRequest is sent by the TDP server to the client
+/// to cancel a previously established watch on path.
+#[derive(Debug)]
+pub struct SharedDirectoryUnwatchRequest {
+    pub completion_id: u32,
+    pub directory_id: u32,
+    pub path: UnixPath,
+}
+
+/// SharedDirectoryUnwatchResponse is sent by the TDP client to the server
+/// to acknowledge a SharedDirectoryUnwatchRequest was received and executed.
+#[derive(Debug)]
+#[repr(C)]
+pub struct SharedDirectoryUnwatchResponse {
+    pub completion_id: u32,
+    pub err_code: TdpErrCode,
+}
+
+/// SharedDirectoryChangeNotification is sent by the TDP client to the
+/// server, unsolicited, whenever a change occurs under a watched path.
+/// The client is responsible for coalescing rapid duplicate events over
+/// a short debounce window and for never reporting a path outside the
+/// directory the watch was registered against.
+#[derive(Debug)]
+pub struct SharedDirectoryChangeNotification {
+    pub directory_id: u32,
+    pub path: UnixPath,
+    pub event_type: ChangeEventType,
+}
+
+impl From<CGOSharedDirectoryChangeNotification> for SharedDirectoryChangeNotification {
+    fn from(cgo: CGOSharedDirectoryChangeNotification) -> SharedDirectoryChangeNotification {
+        // # Safety
+        //
+        // This function MUST NOT hang on to any of the pointers passed in to it after it returns.
+        // In other words, all pointer data that needs to persist after this function returns MUST
+        // be copied into Rust-owned memory.
+        unsafe {
+            SharedDirectoryChangeNotification {
+                directory_id: cgo.directory_id,
+                path: UnixPath::from(from_c_string(cgo.path)),
+                event_type: cgo.event_type,
+            }
+        }
+    }
+}
+
+/// ChangeEventType represents the kind of change a
+/// SharedDirectoryChangeNotification is reporting.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ChangeEventType {
+    /// a new file or directory was created
+    Created = 0,
+    /// an existing file or directory was modified
+    Modified = 1,
+    /// a file or directory was deleted
+    Deleted = 2,
+    /// a file or directory was moved or renamed
+    Moved = 3,
+}
+
+/// ChunkEntry describes one content-defined chunk of a file, as produced
+/// by a rolling-hash (Rabin/Gear) chunker targeting ~64 KiB chunks, so
+/// that boundaries stay stable across insertions into the file.
+#[derive(Debug, Clone)]
+pub struct ChunkEntry {
+    pub offset: u64,
+    pub length: u32,
+    pub digest: [u8; 32],
+}
+
+impl From<CGOChunkEntry> for ChunkEntry {
+    fn from(cgo: CGOChunkEntry) -> ChunkEntry {
+        // # Safety
+        //
+        // This function MUST NOT hang on to any of the pointers passed in to it after it returns.
+        // In other words, all pointer data that needs to persist after this function returns MUST
+        // be copied into Rust-owned memory.
+        unsafe {
+            let digest_bytes: Vec<u8> = from_go_array(cgo.digest, 32);
+            let mut digest = [0u8; 32];
+            digest.copy_from_slice(&digest_bytes);
+
+            ChunkEntry {
+                offset: cgo.offset,
+                length: cgo.length,
+                digest,
+            }
+        }
+    }
+}
+
+/// SharedDirectoryChunkManifestRequest is sent by the TDP server to the
+/// client to request the content-defined chunk manifest of a file, so
+/// the server can fetch only the chunks it doesn't already have cached.
+#[derive(Debug)]
+pub struct SharedDirectoryChunkManifestRequest {
+    pub completion_id: u32,
+    pub directory_id: u32,
+    pub path: UnixPath,
+}
+
+/// SharedDirectoryChunkManifestResponse is sent by the TDP client to the
+/// server with the chunk manifest requested by a
+/// SharedDirectoryChunkManifestRequest.
+#[derive(Debug)]
+pub struct SharedDirectoryChunkManifestResponse {
+    pub completion_id: u32,
+    pub err_code: TdpErrCode,
+    pub chunks: Vec<ChunkEntry>,
+}
+
+impl From<CGOSharedDirectoryChunkManifestResponse> for SharedDirectoryChunkManifestResponse {
+    fn from(cgo: CGOSharedDirectoryChunkManifestResponse) -> SharedDirectoryChunkManifestResponse {
+        // # Safety
+        //
+        // This function MUST NOT hang on to any of the pointers passed in to it after it returns.
+        // In other words, all pointer data that needs to persist after this function returns MUST
+        // be copied into Rust-owned memory.
+        unsafe {
+            let cgo_chunks = from_go_array(cgo.chunks, cgo.chunks_length);
+            let mut chunks = vec![];
+            for cgo_chunk in cgo_chunks.into_iter() {
+                chunks.push(ChunkEntry::from(cgo_chunk));
+            }
+
+            SharedDirectoryChunkManifestResponse {
+                completion_id: cgo.completion_id,
+                err_code: cgo.err_code,
+                chunks,
+            }
+        }
+    }
+}
+
+/// SharedDirectoryChunkFetchRequest is sent by the TDP server to the
+/// client to request the bytes of a single chunk, identified by the
+/// digest from a SharedDirectoryChunkManifestResponse the server does
+/// not already hold in its digest-to-bytes cache.
+#[derive(Debug)]
+pub struct SharedDirectoryChunkFetchRequest {
+    pub completion_id: u32,
+    pub directory_id: u32,
+    pub digest: [u8; 32],
+}
+
+/// SharedDirectoryChunkFetchResponse is sent by the TDP client to the
+/// server with the bytes of the chunk requested by a
+/// SharedDirectoryChunkFetchRequest.
+#[repr(C)]
+pub struct SharedDirectoryChunkFetchResponse {
+    pub completion_id: u32,
+    pub err_code: TdpErrCode,
+    pub chunk_data: Vec<u8>,
+}
+
+impl std::fmt::Debug for SharedDirectoryChunkFetchResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedDirectoryChunkFetchResponse")
+            .field("completion_id", &self.completion_id)
+            .field("err_code", &self.err_code)
+            .field("chunk_data", &util::vec_u8_debug(&self.chunk_data))
+            .finish()
+    }
+}
+
+impl From<CGOSharedDirectoryChunkFetchResponse> for SharedDirectoryChunkFetchResponse {
+    fn from(cgo_response: CGOSharedDirectoryChunkFetchResponse) -> SharedDirectoryChunkFetchResponse {
+        unsafe {
+            SharedDirectoryChunkFetchResponse {
+                completion_id: cgo_response.completion_id,
+                err_code: cgo_response.err_code,
+                chunk_data: from_go_array(cgo_response.chunk_data, cgo_response.chunk_data_length),
+            }
+        }
+    }
+}
+
+/// BatchOp wraps a single operation carried inside a
+/// SharedDirectoryBatchRequest, preserving that operation's own
+/// completion_id so existing server-side completion handlers keep
+/// working unchanged.
+#[derive(Debug)]
+pub enum BatchOp {
+    Info(SharedDirectoryInfoRequest),
+    Create(SharedDirectoryCreateRequest),
+    Write(SharedDirectoryWriteRequest),
+    Read(SharedDirectoryReadRequest),
+    Delete(SharedDirectoryDeleteRequest),
+    Move(SharedDirectoryMoveRequest),
+    List(SharedDirectoryListRequest),
+    Truncate(SharedDirectoryTruncateRequest),
+}
+
+/// SharedDirectoryBatchRequest is sent by the TDP server to the client
+/// to request that a sequence of operations be executed in order
+/// without a network round trip between each one. If stop_on_error is
+/// set, the client aborts at the first failing op instead of running
+/// best-effort to completion.
+#[derive(Debug)]
+pub struct SharedDirectoryBatchRequest {
+    pub batch_id: u32,
+    pub directory_id: u32,
+    pub ops: Vec<BatchOp>,
+    pub stop_on_error: u8,
+}
+
+/// BatchResult carries the outcome of a single BatchOp, wrapping that
+/// op's own real response type (complete with its own completion_id and
+/// err_code) so e.g. a batched Info op still hands back its
+/// FileSystemObject and a batched Read op its bytes, instead of
+/// collapsing every op down to a bare error code.
+#[derive(Debug)]
+pub enum BatchResult {
+    Info(SharedDirectoryInfoResponse),
+    Create(SharedDirectoryCreateResponse),
+    Write(SharedDirectoryWriteResponse),
+    Read(SharedDirectoryReadResponse),
+    Delete(SharedDirectoryDeleteResponse),
+    Move(SharedDirectoryMoveResponse),
+    List(SharedDirectoryListResponse),
+    Truncate(SharedDirectoryTruncateResponse),
+    /// Unknown is the result for a batch entry whose op_kind tag didn't
+    /// match any op this client knows about (e.g. Rust/Go enum skew, or
+    /// a corrupted response). It's treated as a normal failure of that
+    /// one op rather than a reason to crash the whole client.
+    Unknown {
+        completion_id: u32,
+        err_code: TdpErrCode,
+    },
+}
+
+/// SharedDirectoryBatchResponse is sent by the TDP client to the server
+/// with the per-op results of a SharedDirectoryBatchRequest. When
+/// stop_on_error aborted the batch early, failed_index holds the index
+/// of the op that failed; results will then be shorter than ops.
+#[derive(Debug)]
+pub struct SharedDirectoryBatchResponse {
+    pub batch_id: u32,
+    pub results: Vec<BatchResult>,
+    pub failed_index: Option<u32>,
+}
+
+impl From<CGOSharedDirectoryBatchResponse> for SharedDirectoryBatchResponse {
+    fn from(cgo: CGOSharedDirectoryBatchResponse) -> SharedDirectoryBatchResponse {
+        // # Safety
+        //
+        // This function MUST NOT hang on to any of the pointers passed in to it after it returns.
+        // In other words, all pointer data that needs to persist after this function returns MUST
+        // be copied into Rust-owned memory.
+        unsafe {
+            let cgo_results = from_go_array(cgo.results, cgo.results_length);
+            let mut results = vec![];
+            for cgo_result in cgo_results.into_iter() {
+                results.push(BatchResult::from(cgo_result));
+            }
+
+            SharedDirectoryBatchResponse {
+                batch_id: cgo.batch_id,
+                results,
+                failed_index: if cgo.failed_index == u32::MAX {
+                    None
+                } else {
+                    Some(cgo.failed_index)
+                },
+            }
+        }
+    }
+}
+
+impl From<CGOBatchResult> for BatchResult {
+    fn from(cgo: CGOBatchResult) -> BatchResult {
+        // # Safety
+        //
+        // This function MUST NOT hang on to any of the pointers passed in to it after it returns.
+        // In other words, all pointer data that needs to persist after this function returns MUST
+        // be copied into Rust-owned memory.
+        unsafe {
+            match cgo.op_kind {
+                0 => BatchResult::Info(SharedDirectoryInfoResponse::from(cgo.info)),
+                1 => BatchResult::Create(SharedDirectoryCreateResponse::from(cgo.create)),
+                2 => BatchResult::Write(cgo.write),
+                3 => BatchResult::Read(SharedDirectoryReadResponse::from(cgo.read)),
+                4 => BatchResult::Delete(cgo.delete),
+                5 => BatchResult::Move(cgo.r#move),
+                6 => BatchResult::List(SharedDirectoryListResponse::from(cgo.list)),
+                7 => BatchResult::Truncate(cgo.truncate),
+                _ => BatchResult::Unknown {
+                    completion_id: cgo.completion_id,
+                    err_code: TdpErrCode::Failed,
+                },
+            }
+        }
+    }
+}
+
+/// SharedDirectoryRecursiveDeleteRequest is sent by the TDP server to the
+/// client to request that path, along with everything under it, be
+/// deleted locally by the client without the server having to drive a
+/// manual recursion over list/delete requests. The client walks the
+/// subtree depth-first so children are removed before their parents,
+/// and does not follow symlinks across the tree boundary.
+#[derive(Debug)]
+pub struct SharedDirectoryRecursiveDeleteRequest {
+    pub completion_id: u32,
+    pub directory_id: u32,
+    pub path: UnixPath,
+}
+
+/// SharedDirectoryRecursiveCopyRequest is sent by the TDP server to the
+/// client to request that the subtree at source_path be copied to
+/// dest_path entirely on the client, creating each directory before its
+/// contents and not following symlinks across the tree boundary.
+#[derive(Debug)]
+pub struct SharedDirectoryRecursiveCopyRequest {
+    pub completion_id: u32,
+    pub directory_id: u32,
+    pub source_path: UnixPath,
+    pub dest_path: UnixPath,
+}
+
+/// SharedDirectoryRecursiveProgress is sent by the TDP client to the
+/// server, unsolicited, over the course of a recursive copy or delete
+/// so the server can show progress and cancel.
+#[derive(Debug)]
+pub struct SharedDirectoryRecursiveProgress {
+    pub completion_id: u32,
+    pub entries_done: u64,
+    pub bytes_done: u64,
+    pub current_path: UnixPath,
+}
+
+impl From<CGOSharedDirectoryRecursiveProgress> for SharedDirectoryRecursiveProgress {
+    fn from(cgo: CGOSharedDirectoryRecursiveProgress) -> SharedDirectoryRecursiveProgress {
+        // # Safety
+        //
+        // This function MUST NOT hang on to any of the pointers passed in to it after it returns.
+        // In other words, all pointer data that needs to persist after this function returns MUST
+        // be copied into Rust-owned memory.
+        unsafe {
+            SharedDirectoryRecursiveProgress {
+                completion_id: cgo.completion_id,
+                entries_done: cgo.entries_done,
+                bytes_done: cgo.bytes_done,
+                current_path: UnixPath::from(from_c_string(cgo.current_path)),
+            }
+        }
+    }
+}
+
+/// SharedDirectoryRecursiveResponse is sent by the TDP client to the
+/// server to report the terminal outcome of a
+/// SharedDirectoryRecursiveDeleteRequest or
+/// SharedDirectoryRecursiveCopyRequest. On a partial failure mid-walk,
+/// failed_path holds the first path that failed rather than leaving the
+/// server guessing.
+#[derive(Debug)]
+pub struct SharedDirectoryRecursiveResponse {
+    pub completion_id: u32,
+    pub err_code: TdpErrCode,
+    pub entries_processed: u64,
+    pub failed_path: Option<UnixPath>,
+}
+
+impl From<CGOSharedDirectoryRecursiveResponse> for SharedDirectoryRecursiveResponse {
+    fn from(cgo: CGOSharedDirectoryRecursiveResponse) -> SharedDirectoryRecursiveResponse {
+        // # Safety
+        //
+        // This function MUST NOT hang on to any of the pointers passed in to it after it returns.
+        // In other words, all pointer data that needs to persist after this function returns MUST
+        // be copied into Rust-owned memory.
+        unsafe {
+            SharedDirectoryRecursiveResponse {
+                completion_id: cgo.completion_id,
+                err_code: cgo.err_code,
+                entries_processed: cgo.entries_processed,
+                failed_path: if cgo.failed_path.is_null() {
+                    None
+                } else {
+                    Some(UnixPath::from(from_c_string(cgo.failed_path)))
+                },
+            }
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum TdpErrCode {
@@ -313,4 +741,37 @@ pub enum TdpErrCode {
 pub enum FileType {
     File = 0,
     Directory = 1,
+    Symlink = 2,
+}
+
+/// WriteMode distinguishes the positional and append write semantics of
+/// a SharedDirectoryWriteRequest.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum WriteMode {
+    /// write at the request's offset
+    Normal = 0,
+    /// ignore the request's offset and write at the current end-of-file
+    Append = 1,
+}
+
+/// SharedDirectoryTruncateRequest is sent by the TDP server to the client
+/// to set a file's size directly, without having to write zero bytes to
+/// extend it or issue a separate delete-and-recreate to shrink it.
+#[derive(Debug)]
+pub struct SharedDirectoryTruncateRequest {
+    pub completion_id: u32,
+    pub directory_id: u32,
+    pub path: UnixPath,
+    pub end_of_file: u64,
+}
+
+/// SharedDirectoryTruncateResponse is sent by the TDP client to the
+/// server to acknowledge a SharedDirectoryTruncateRequest was received
+/// and executed.
+#[derive(Debug)]
+#[repr(C)]
+pub struct SharedDirectoryTruncateResponse {
+    pub completion_id: u32,
+    pub err_code: TdpErrCode,
 }